@@ -0,0 +1,82 @@
+//! Optional EIP-2930 access lists for submission transactions.
+//!
+//! Every `addRandomNumbers`/`addRandomNumber` call touches the same predictable
+//! storage slots and the ECVRF verifier contract, so warming them with an access
+//! list measurably reduces gas, especially for large batches. [`AccessListMode`]
+//! either applies a static operator-supplied list or asks the node to compute
+//! one with `eth_createAccessList` against the populated transaction, and can be
+//! disabled entirely on chains that do not support typed transactions.
+
+use std::env;
+
+use alloy::{
+    network::Network,
+    primitives::{Address, Bytes},
+    providers::Provider,
+    rpc::types::AccessList,
+};
+use color_eyre::Result;
+use log::debug;
+
+/// How an access list is sourced for a submission.
+#[derive(Debug, Clone, Default)]
+pub enum AccessListMode {
+    /// No access list; the transaction is sent as a plain 1559/legacy type.
+    #[default]
+    Disabled,
+    /// A fixed, operator-supplied list applied to every submission.
+    Static(AccessList),
+    /// Computed per-transaction via `eth_createAccessList`.
+    Dynamic,
+}
+
+impl AccessListMode {
+    /// Reads the mode from the environment.
+    ///
+    /// `ACCESS_LIST=dynamic` computes a list per transaction; `ACCESS_LIST=static`
+    /// uses the JSON list in `ACCESS_LIST_JSON`; anything else disables the
+    /// feature.
+    pub fn from_env() -> Result<Self> {
+        match env::var("ACCESS_LIST").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "dynamic" => Ok(Self::Dynamic),
+            "static" => {
+                let json = env::var("ACCESS_LIST_JSON")?;
+                Ok(Self::Static(serde_json::from_str(&json)?))
+            }
+            _ => Ok(Self::Disabled),
+        }
+    }
+
+    /// Resolves the access list for a transaction from `from` to `to` carrying
+    /// `calldata`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `eth_createAccessList` request fails in
+    /// [`Dynamic`](Self::Dynamic) mode.
+    pub async fn resolve<P, N>(
+        &self,
+        provider: &P,
+        from: Address,
+        to: Address,
+        calldata: &Bytes,
+    ) -> Result<Option<AccessList>>
+    where
+        P: Provider<N>,
+        N: Network,
+    {
+        match self {
+            Self::Disabled => Ok(None),
+            Self::Static(list) => Ok(Some(list.clone())),
+            Self::Dynamic => {
+                let request = serde_json::json!({ "from": from, "to": to, "data": calldata });
+                let result: serde_json::Value = provider
+                    .raw_request(std::borrow::Cow::Borrowed("eth_createAccessList"), [request])
+                    .await?;
+                let list: AccessList = serde_json::from_value(result["accessList"].clone())?;
+                debug!("Resolved dynamic access list with {} entries", list.len());
+                Ok(Some(list))
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Gas pricing strategy for number submission.
+//!
+//! Without fee control, batches sent with alloy defaults get stuck or overpay
+//! during congestion. [`GasStrategy`] is threaded through the send path and
+//! offers a legacy (`gasPrice`) mode and an EIP-1559 mode where `maxFeePerGas`
+//! and `maxPriorityFeePerGas` are derived from the provider's recent base fee
+//! plus a configurable priority tip and multiplier, with a ceiling that refuses
+//! submission when the base fee is absurd. Stuck transactions are bumped and
+//! resubmitted at the same nonce.
+
+use std::{env, time::Duration};
+
+use alloy::{eips::BlockId, network::Network, providers::Provider};
+use color_eyre::{eyre::eyre, Result};
+
+/// Default seconds to wait for a receipt before bumping fees and resubmitting.
+const DEFAULT_RESUBMIT_TIMEOUT_SECS: u64 = 90;
+/// Default number of fee bumps attempted before giving up on a stuck transaction.
+const DEFAULT_MAX_RESUBMISSIONS: u32 = 3;
+/// Default per-bump fee increase, as a percentage of the previous fees.
+const DEFAULT_BUMP_PERCENT: u128 = 15;
+
+/// How submission fees are priced.
+#[derive(Debug, Clone)]
+pub enum GasStrategy {
+    /// Legacy pricing. `gas_price` is honoured when set, otherwise the provider
+    /// estimate is used.
+    Legacy { gas_price: Option<u128> },
+    /// EIP-1559 pricing derived from the recent base fee.
+    Eip1559 {
+        /// Priority tip (`maxPriorityFeePerGas`), in wei.
+        priority_tip_wei: u128,
+        /// Base-fee multiplier applied to `maxFeePerGas`, as a percentage
+        /// (e.g. `200` leaves headroom for one base-fee doubling).
+        base_fee_multiplier_percent: u128,
+        /// Refuse submission when the base fee exceeds this ceiling, in wei.
+        max_base_fee_wei: u128,
+    },
+}
+
+/// Concrete fee fields resolved from a [`GasStrategy`] against live chain state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolvedFees {
+    Legacy { gas_price: u128 },
+    Eip1559 { max_fee_per_gas: u128, max_priority_fee_per_gas: u128 },
+}
+
+impl GasStrategy {
+    /// Reads the strategy from the environment.
+    ///
+    /// `GAS_STRATEGY=eip1559` selects the 1559 mode (tuned by
+    /// `GAS_PRIORITY_TIP_WEI`, `GAS_BASE_FEE_MULTIPLIER_PERCENT` and
+    /// `GAS_MAX_BASE_FEE_WEI`); anything else selects legacy mode, optionally
+    /// pinned with `GAS_PRICE_WEI`.
+    pub fn from_env() -> Result<Self> {
+        match env::var("GAS_STRATEGY").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "eip1559" | "1559" => Ok(Self::Eip1559 {
+                priority_tip_wei: parse_env_u128("GAS_PRIORITY_TIP_WEI", 1_500_000_000)?,
+                base_fee_multiplier_percent: parse_env_u128(
+                    "GAS_BASE_FEE_MULTIPLIER_PERCENT",
+                    200,
+                )?,
+                max_base_fee_wei: parse_env_u128("GAS_MAX_BASE_FEE_WEI", 500_000_000_000)?,
+            }),
+            _ => {
+                let gas_price = match env::var("GAS_PRICE_WEI") {
+                    Ok(value) => Some(value.parse()?),
+                    Err(_) => None,
+                };
+                Ok(Self::Legacy { gas_price })
+            }
+        }
+    }
+
+    /// How long to wait for a receipt before bumping and resubmitting.
+    pub fn resubmit_timeout(&self) -> Duration {
+        Duration::from_secs(
+            env::var("GAS_RESUBMIT_TIMEOUT_SECS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_RESUBMIT_TIMEOUT_SECS),
+        )
+    }
+
+    /// Maximum number of fee bumps before a stuck transaction is surfaced as an
+    /// error.
+    pub fn max_resubmissions(&self) -> u32 {
+        env::var("GAS_MAX_RESUBMISSIONS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MAX_RESUBMISSIONS)
+    }
+
+    /// Resolves concrete fee fields from the provider's recent base fee.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the latest block cannot be read, or when the base
+    /// fee exceeds `max_base_fee_wei` in EIP-1559 mode.
+    pub async fn resolve<P, N>(&self, provider: &P) -> Result<ResolvedFees>
+    where
+        P: Provider<N>,
+        N: Network,
+    {
+        match self {
+            Self::Legacy { gas_price: Some(gas_price) } => {
+                Ok(ResolvedFees::Legacy { gas_price: *gas_price })
+            }
+            Self::Legacy { gas_price: None } => {
+                Ok(ResolvedFees::Legacy { gas_price: provider.get_gas_price().await? })
+            }
+            Self::Eip1559 { priority_tip_wei, base_fee_multiplier_percent, max_base_fee_wei } => {
+                let base_fee = provider
+                    .get_block(BlockId::latest())
+                    .await?
+                    .and_then(|block| block.header().base_fee_per_gas())
+                    .ok_or_else(|| eyre!("latest block carries no base fee; chain is not EIP-1559"))?
+                    as u128;
+
+                if base_fee > *max_base_fee_wei {
+                    return Err(eyre!(
+                        "base fee {base_fee} wei exceeds ceiling {max_base_fee_wei} wei; refusing to submit"
+                    ));
+                }
+
+                Ok(ResolvedFees::Eip1559 {
+                    max_fee_per_gas: eip1559_max_fee(
+                        base_fee,
+                        *base_fee_multiplier_percent,
+                        *priority_tip_wei,
+                    ),
+                    max_priority_fee_per_gas: *priority_tip_wei,
+                })
+            }
+        }
+    }
+}
+
+impl ResolvedFees {
+    /// Increases every fee field by [`DEFAULT_BUMP_PERCENT`], used when a
+    /// transaction is stuck and must be resubmitted at the same nonce.
+    pub fn bumped(self) -> Self {
+        let bump = |fee: u128| fee + fee.saturating_mul(DEFAULT_BUMP_PERCENT) / 100;
+        match self {
+            Self::Legacy { gas_price } => Self::Legacy { gas_price: bump(gas_price) },
+            Self::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => Self::Eip1559 {
+                max_fee_per_gas: bump(max_fee_per_gas),
+                max_priority_fee_per_gas: bump(max_priority_fee_per_gas),
+            },
+        }
+    }
+}
+
+/// `maxFeePerGas` from a base fee: `base_fee * multiplier_percent / 100 + tip`.
+fn eip1559_max_fee(base_fee: u128, multiplier_percent: u128, tip: u128) -> u128 {
+    base_fee.saturating_mul(multiplier_percent) / 100 + tip
+}
+
+fn parse_env_u128(key: &str, default: u128) -> Result<u128> {
+    match env::var(key) {
+        Ok(value) => Ok(value.parse()?),
+        Err(_) => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_fee_applies_multiplier_and_tip() {
+        // 100 gwei base, 2x headroom, 1 gwei tip → 201 gwei.
+        assert_eq!(
+            eip1559_max_fee(100_000_000_000, 200, 1_000_000_000),
+            201_000_000_000
+        );
+    }
+
+    #[test]
+    fn bumped_legacy_increases_by_fifteen_percent() {
+        let bumped = ResolvedFees::Legacy { gas_price: 1_000 }.bumped();
+        assert_eq!(bumped, ResolvedFees::Legacy { gas_price: 1_150 });
+    }
+
+    #[test]
+    fn bumped_eip1559_increases_both_fields() {
+        let bumped =
+            ResolvedFees::Eip1559 { max_fee_per_gas: 2_000, max_priority_fee_per_gas: 1_000 }
+                .bumped();
+        assert_eq!(
+            bumped,
+            ResolvedFees::Eip1559 { max_fee_per_gas: 2_300, max_priority_fee_per_gas: 1_150 }
+        );
+    }
+}
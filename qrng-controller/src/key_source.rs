@@ -0,0 +1,110 @@
+//! Pluggable key-management backends.
+//!
+//! The signer and prover identities used to sign QRNs and produce ECVRF proofs
+//! are resolved through a single [`KeySource`] so operators can rotate
+//! credentials without recompiling. Every backend resolves to the same raw
+//! 32-byte secret, from which both the Ethereum [`SignatureSecretKey`] consumed
+//! by `sign_data` and the `libecvrf` [`ProverSecretKey`] are derived — keeping
+//! the registered VRF public key in lock-step with the signer address.
+//!
+//! Three backends are supported, following the keystore / brain-seed model of
+//! the `ethkey` tooling:
+//!
+//! * [`KeySource::PrivateKey`] — a raw hex key, as read from the environment
+//!   today.
+//! * [`KeySource::Keystore`] — an encrypted JSON keystore unlocked with a
+//!   passphrase.
+//! * [`KeySource::Mnemonic`] — deterministic derivation from a seed phrase along
+//!   a derivation path.
+
+use std::{env, path::PathBuf};
+
+use alloy::signers::local::{
+    coins_bip39::English, MnemonicBuilder, PrivateKeySigner,
+};
+use color_eyre::{eyre::eyre, Result};
+use ethsign::SecretKey as SignatureSecretKey;
+use libecvrf::secp256k1::SecretKey as ProverSecretKey;
+
+/// Default BIP-44 derivation path for the first Ethereum account.
+const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// A backend that yields the signing identity used to produce QRNs.
+#[derive(Debug, Clone)]
+pub enum KeySource {
+    /// A raw secret key, hex-encoded with or without a `0x` prefix.
+    PrivateKey(String),
+    /// An encrypted JSON keystore file unlocked with `passphrase`.
+    Keystore { path: PathBuf, passphrase: String },
+    /// A mnemonic seed phrase, derived along `derivation_path`.
+    Mnemonic { phrase: String, derivation_path: String },
+}
+
+impl KeySource {
+    /// Selects a backend from the environment.
+    ///
+    /// `KEYSTORE_PATH` (with `KEYSTORE_PASSPHRASE`) takes precedence, then
+    /// `MNEMONIC` (with an optional `DERIVATION_PATH`), and finally the raw
+    /// `PRIVATE_KEY_WALLET` hex key used historically.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when none of the supported variables are set.
+    pub fn from_env() -> Result<Self> {
+        Self::from_env_prefixed("")
+    }
+
+    /// As [`from_env`](Self::from_env), but reads variables prefixed with
+    /// `prefix` (e.g. `NEW_` for a rotation target), so a second identity can
+    /// coexist with the live one.
+    pub fn from_env_prefixed(prefix: &str) -> Result<Self> {
+        let var = |name: &str| env::var(format!("{prefix}{name}"));
+
+        if let Ok(path) = var("KEYSTORE_PATH") {
+            let passphrase = var("KEYSTORE_PASSPHRASE")?;
+            return Ok(Self::Keystore { path: path.into(), passphrase });
+        }
+        if let Ok(phrase) = var("MNEMONIC") {
+            let derivation_path =
+                var("DERIVATION_PATH").unwrap_or_else(|_| DEFAULT_DERIVATION_PATH.to_string());
+            return Ok(Self::Mnemonic { phrase, derivation_path });
+        }
+        let private_key = var("PRIVATE_KEY_WALLET").map_err(|_| {
+            eyre!("no key source configured (set {prefix}KEYSTORE_PATH, {prefix}MNEMONIC or {prefix}PRIVATE_KEY_WALLET)")
+        })?;
+        Ok(Self::PrivateKey(private_key))
+    }
+
+    /// Resolves the backend to an alloy [`PrivateKeySigner`] — the wallet the
+    /// binary signs transactions with.
+    pub fn signer(&self) -> Result<PrivateKeySigner> {
+        match self {
+            Self::PrivateKey(hex) => Ok(hex.parse()?),
+            Self::Keystore { path, passphrase } => {
+                Ok(PrivateKeySigner::decrypt_keystore(path, passphrase)?)
+            }
+            Self::Mnemonic { phrase, derivation_path } => Ok(MnemonicBuilder::<English>::default()
+                .phrase(phrase.as_str())
+                .derivation_path(derivation_path)?
+                .build()?),
+        }
+    }
+
+    /// The raw 32-byte secret backing both derived keys.
+    pub fn secret_bytes(&self) -> Result<[u8; 32]> {
+        Ok(self.signer()?.to_bytes().into())
+    }
+
+    /// The Ethereum signing key consumed by `sign_data`.
+    pub fn signing_key(&self) -> Result<SignatureSecretKey> {
+        SignatureSecretKey::from_raw(&self.secret_bytes()?)
+            .map_err(|e| eyre!("invalid Ethereum signing key: {e:?}"))
+    }
+
+    /// The `libecvrf` prover key; derived from the same secret so the VRF public
+    /// key always matches the signer.
+    pub fn prover_key(&self) -> Result<ProverSecretKey> {
+        ProverSecretKey::parse_slice(&self.secret_bytes()?)
+            .map_err(|e| eyre!("invalid ECVRF prover key: {e:?}"))
+    }
+}
@@ -17,7 +17,19 @@ use color_eyre::{Result, eyre::eyre};
 use foundry_contracts::storage_number::StorageNumber::{
     self, EcvrfContractProofSolidity, SignatureSolidity,
 };
-use log::debug;
+use log::{debug, warn};
+
+pub mod access_list;
+pub mod gas;
+pub mod key_source;
+pub mod lifecycle;
+pub mod rotation;
+pub mod subscription;
+pub mod verify;
+
+use access_list::AccessListMode;
+use gas::{GasStrategy, ResolvedFees};
+use lifecycle::PendingBatch;
 
 pub type SignedNumber = (U256, FixedBytes<32>, SignatureSolidity, EcvrfContractProofSolidity);
 
@@ -70,65 +82,195 @@ impl<T: HardwareInterface> SignedGenerator for T {
 /// * `generation_left` - Number of QRNs requested by the contract.
 /// * `rng` - Generator providing QRNs (either hardware or mock).
 /// * `nonce` - Nonce to use for this generation batch.
+/// * `gas` - Fee strategy used to price the submission and to bump stuck transactions.
+/// * `confirmations` - Number of block confirmations required before the batch is complete.
 ///
 /// # Errors
 ///
 /// Returns a `color_eyre::Report` if:
 //  - The generation fails.
+//  - Local verification rejects an element.
 //  - The transaction is reverted.
 //  - The receipt shows a failed status.
+//  - The transaction stays stuck past the resubmission budget.
 pub async fn send_random_number<P, N>(
     contract_qrng: &StorageNumber::StorageNumberInstance<P, N>,
     wallet_address: Address,
     generation_left: U256,
     rng: &mut impl SignedGenerator,
     nonce: u64,
+    gas: &GasStrategy,
+    confirmations: u64,
+    access_list: &AccessListMode,
 ) -> color_eyre::Result<()>
 where
     P: Provider<N>,
     N: Network,
 {
-    let mut data = vec![];
-    let mut hashes = vec![];
-    let mut signatures = vec![];
-    let mut proofs = vec![];
+    let batch = build_batch(rng, generation_left, nonce, wallet_address)?;
+    submit_batch(contract_qrng, wallet_address, &batch, gas, confirmations, access_list).await
+}
 
+/// Generates a batch of signed QRNs and verifies each element locally.
+///
+/// The returned [`PendingBatch`] carries the full submission payload so it can be
+/// persisted and resubmitted verbatim without regenerating randomness.
+///
+/// # Errors
+///
+/// Returns an error if generation fails or any element fails local verification.
+pub fn build_batch(
+    rng: &mut impl SignedGenerator,
+    generation_left: U256,
+    nonce: u64,
+    wallet_address: Address,
+) -> color_eyre::Result<PendingBatch> {
     let gen_left = generation_left.into_limbs()[0] as u32;
     let signed_qrn_data = rng.get_signed(gen_left, nonce, wallet_address)?;
+
+    // Reject anything that would revert on-chain before spending gas on it.
+    verify::verify_signed_numbers(&signed_qrn_data, wallet_address)?;
+
+    let mut batch = PendingBatch {
+        nonce,
+        single: generation_left == U256::from(1),
+        data: vec![],
+        hashes: vec![],
+        signatures: vec![],
+        proofs: vec![],
+    };
     for (random_data_u256, hash, sig, proof) in signed_qrn_data {
-        data.push(random_data_u256);
-        hashes.push(hash);
-        signatures.push(sig);
-        proofs.push(proof);
+        batch.data.push(random_data_u256);
+        batch.hashes.push(hash);
+        batch.signatures.push(sig);
+        batch.proofs.push(proof);
     }
-    let tx_qrng = if generation_left != U256::from(1) {
-        contract_qrng
-            .addRandomNumbers(data.clone(), signatures, hashes, proofs)
-            .from(wallet_address)
-            .send()
-            .await?
-    } else {
-        contract_qrng
-            .addRandomNumber(data[0], signatures[0].clone(), hashes[0], proofs[0].clone())
-            .from(wallet_address)
-            .send()
-            .await?
+    Ok(batch)
+}
+
+/// Submits a previously built [`PendingBatch`] and waits for `confirmations`
+/// blocks before returning.
+///
+/// A transaction stuck past the strategy's resubmit timeout is bumped and
+/// resubmitted at the same nonce until the budget is exhausted, so the batch's
+/// QRN payload is never regenerated.
+///
+/// # Errors
+///
+/// Returns an error when the batch still reverts or stays stuck after the
+/// resubmission budget is exhausted, or on any RPC failure.
+pub async fn submit_batch<P, N>(
+    contract_qrng: &StorageNumber::StorageNumberInstance<P, N>,
+    wallet_address: Address,
+    batch: &PendingBatch,
+    gas: &GasStrategy,
+    confirmations: u64,
+    access_list: &AccessListMode,
+) -> color_eyre::Result<()>
+where
+    P: Provider<N>,
+    N: Network,
+{
+    let PendingBatch { nonce, single, data, hashes, signatures, proofs } = batch;
+    let nonce = *nonce;
+
+    let mut fees = gas.resolve(contract_qrng.provider()).await?;
+    let timeout = gas.resubmit_timeout();
+
+    // Pin the Ethereum account nonce across fee bumps. It is the account's
+    // transaction nonce — read from the chain, including pending transactions —
+    // NOT the contract's QRN nonce (which only seeds `get_signed`). Reusing it
+    // across fee bumps makes each resubmission a true replacement of the stuck
+    // transaction rather than a fresh one queued behind it, so the same QRN
+    // payload is never mined twice. A reverted transaction is mined and consumes
+    // its nonce, so the nonce is read again before resubmitting after a revert.
+    let mut account_nonce =
+        contract_qrng.provider().get_transaction_count(wallet_address).pending().await?;
+
+    // Warm the storage slots and verifier contract the batch touches; the
+    // payload is fixed, so the list is resolved once and reused across bumps.
+    let build_call = |fees, account_nonce: u64| {
+        let call = if !single {
+            contract_qrng
+                .addRandomNumbers(data.clone(), signatures.clone(), hashes.clone(), proofs.clone())
+        } else {
+            contract_qrng.addRandomNumber(data[0], signatures[0].clone(), hashes[0], proofs[0].clone())
+        };
+        apply_fees(call.from(wallet_address).nonce(account_nonce), fees)
     };
+    let access = access_list
+        .resolve(
+            contract_qrng.provider(),
+            wallet_address,
+            *contract_qrng.address(),
+            &build_call(fees, account_nonce).calldata().clone(),
+        )
+        .await?;
+
+    // Submit, then wait for confirmations. A transaction that is stuck past the
+    // timeout OR that reverts/reorgs is bumped and resubmitted with the same QRN
+    // payload until the budget is exhausted.
+    let mut last_revert = None;
+    for attempt in 0..=gas.max_resubmissions() {
+        if attempt > 0 {
+            fees = fees.bumped();
+            debug!("Resubmitting QRN nonce {nonce} with bumped fees {fees:?}");
+        }
+
+        let mut call = build_call(fees, account_nonce);
+        if let Some(list) = &access {
+            call = call.access_list(list.clone());
+        }
+        let tx_qrng = call.send().await?.with_required_confirmations(confirmations);
+
+        let receipt = match tokio::time::timeout(timeout, tx_qrng.get_receipt()).await {
+            Ok(receipt) => receipt?,
+            Err(_) => continue,
+        };
 
-    let receipt = tx_qrng.get_receipt().await?;
-    if receipt.status() {
-        for bytes in data {
-            debug!("Number generated: {bytes:?}");
+        if receipt.status() {
+            for bytes in data {
+                debug!("Number generated: {bytes:?}");
+            }
+            return Ok(());
         }
-    } else {
+
+        // Reverted: capture the trace and resubmit rather than aborting.
         let tx_hash = receipt.transaction_hash();
         let trace: serde_json::Value = contract_qrng
             .provider()
             .raw_request(std::borrow::Cow::Borrowed("debug_traceTransaction"), [tx_hash])
             .await?;
-        return Err(eyre!("Revert reason: {trace:?}"));
+        warn!("Submission at QRN nonce {nonce} reverted on attempt {attempt}: {trace:?}");
+        last_revert = Some(trace);
+        account_nonce =
+            contract_qrng.provider().get_transaction_count(wallet_address).pending().await?;
+    }
+
+    match last_revert {
+        Some(trace) => Err(eyre!("QRN nonce {nonce} still reverting after resubmissions: {trace:?}")),
+        None => Err(eyre!(
+            "transaction for QRN nonce {nonce} stuck after {} resubmissions",
+            gas.max_resubmissions()
+        )),
+    }
+}
+
+/// Applies resolved [`ResolvedFees`] onto a pending transaction builder.
+fn apply_fees<P, N, D>(
+    call: alloy::contract::CallBuilder<P, D, N>,
+    fees: ResolvedFees,
+) -> alloy::contract::CallBuilder<P, D, N>
+where
+    P: Provider<N>,
+    N: Network,
+{
+    match fees {
+        ResolvedFees::Legacy { gas_price } => call.gas_price(gas_price),
+        ResolvedFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => {
+            call.max_fee_per_gas(max_fee_per_gas).max_priority_fee_per_gas(max_priority_fee_per_gas)
+        }
     }
-    Ok(())
 }
 
 /// Parses a raw byte buffer into a Solidity-compatible VRF proof structure.
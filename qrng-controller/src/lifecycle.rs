@@ -0,0 +1,222 @@
+//! Durable nonce manager and transaction lifecycle.
+//!
+//! The submitter must survive dropped, stuck and reorged transactions without
+//! losing a batch. [`NonceManager`] allocates nonces locally so batches can be
+//! queued without an RPC round-trip per submission, persists every outstanding
+//! batch to disk, and on restart reloads the pending set and reconciles it
+//! against chain state before the controller resubscribes. Each pending batch
+//! carries its full QRN payload so it is resubmitted verbatim rather than
+//! regenerated.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use alloy::{
+    network::Network,
+    primitives::{Address, FixedBytes, U256},
+    providers::Provider,
+};
+use color_eyre::Result;
+use foundry_contracts::storage_number::StorageNumber::{
+    self, EcvrfContractProofSolidity, SignatureSolidity,
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{access_list::AccessListMode, gas::GasStrategy, submit_batch};
+
+/// A fully built submission payload, persisted so it can be resubmitted verbatim.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingBatch {
+    /// Nonce this batch is committed to.
+    pub nonce: u64,
+    /// Whether the batch is submitted via `addRandomNumber` (single) or
+    /// `addRandomNumbers` (batch).
+    pub single: bool,
+    pub data: Vec<U256>,
+    pub hashes: Vec<FixedBytes<32>>,
+    pub signatures: Vec<SignatureSolidity>,
+    pub proofs: Vec<EcvrfContractProofSolidity>,
+}
+
+impl PendingBatch {
+    /// Recombines the stored columns into the [`SignedNumber`](crate::SignedNumber)
+    /// tuples used by local verification.
+    pub fn to_signed_numbers(&self) -> Vec<crate::SignedNumber> {
+        self.data
+            .iter()
+            .zip(&self.hashes)
+            .zip(&self.signatures)
+            .zip(&self.proofs)
+            .map(|(((data, hash), signature), proof)| {
+                (*data, *hash, signature.clone(), proof.clone())
+            })
+            .collect()
+    }
+}
+
+/// On-disk representation of the outstanding batch set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PersistedState {
+    pending: Vec<PendingBatch>,
+}
+
+/// Tracks nonce allocation and the set of in-flight batches, backed by a file.
+#[derive(Debug)]
+pub struct NonceManager {
+    store_path: PathBuf,
+    next_nonce: u64,
+    /// In-flight batches keyed by nonce.
+    pending: BTreeMap<u64, PendingBatch>,
+    /// Confirmation depth required before a batch is considered complete.
+    confirmations: u64,
+}
+
+impl NonceManager {
+    /// Loads any persisted pending batches from `store_path`, or starts empty.
+    ///
+    /// The next nonce is seeded optimistically from the persisted set and then
+    /// corrected against chain state by [`reconcile`](Self::reconcile).
+    pub fn load(store_path: impl Into<PathBuf>, confirmations: u64) -> Result<Self> {
+        let store_path = store_path.into();
+        let state = match std::fs::read(&store_path) {
+            Ok(bytes) => serde_json::from_slice::<PersistedState>(&bytes)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => PersistedState::default(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let pending: BTreeMap<u64, PendingBatch> =
+            state.pending.into_iter().map(|batch| (batch.nonce, batch)).collect();
+        // A batch of N numbers consumes N QRN nonces starting at batch.nonce.
+        let next_nonce = pending
+            .values()
+            .map(|batch| batch.nonce + batch.data.len().max(1) as u64)
+            .max()
+            .unwrap_or(0);
+
+        Ok(Self { store_path, next_nonce, pending, confirmations })
+    }
+
+    /// Allocates the starting QRN nonce for a batch of `count` numbers, tracking
+    /// it locally so multiple batches can be queued without re-reading
+    /// `getNonce`.
+    ///
+    /// The contract advances its nonce by the batch size (`OfflineGenerator`
+    /// signs `nonce + i` for `i in 0..count`), so the allocator must advance by
+    /// `count` to keep successive batches from signing an overlapping nonce
+    /// range the contract would reject.
+    pub fn allocate_nonce(&mut self, count: u64) -> u64 {
+        let nonce = self.next_nonce;
+        self.next_nonce += count.max(1);
+        nonce
+    }
+
+    /// Reconciles the local nonce cursor against the contract's authoritative
+    /// nonce for `wallet`, resubmitting any persisted pending batch that the
+    /// chain has not yet accepted.
+    ///
+    /// Call on startup, before subscribing to live events; the controller calls
+    /// it again on every reconnect.
+    pub async fn reconcile<P, N>(
+        &mut self,
+        contract: &StorageNumber::StorageNumberInstance<P, N>,
+        wallet: Address,
+        gas: &GasStrategy,
+        access_list: &AccessListMode,
+    ) -> Result<()>
+    where
+        P: Provider<N>,
+        N: Network,
+    {
+        let chain_nonce: u64 = contract.getNonce(wallet).call().await?;
+
+        // Batches below the chain nonce have already been accepted; drop them.
+        let accepted: Vec<u64> =
+            self.pending.keys().copied().filter(|nonce| *nonce < chain_nonce).collect();
+        for nonce in accepted {
+            info!("Batch at nonce {nonce} already accepted on-chain; clearing");
+            self.pending.remove(&nonce);
+        }
+
+        // The local cursor must never fall behind the chain.
+        self.next_nonce = self.next_nonce.max(chain_nonce);
+        self.persist()?;
+
+        // Resubmit everything still outstanding against the current chain state.
+        // A batch that reverts or stays stuck stays local: it is left persisted
+        // and retried on the next reconcile, rather than bubbling an error out of
+        // the controller's supervised reconnect loop.
+        let outstanding: Vec<PendingBatch> = self.pending.values().cloned().collect();
+        for batch in outstanding {
+            let nonce = batch.nonce;
+            warn!("Resubmitting outstanding batch at nonce {nonce}");
+            match submit_batch(contract, wallet, &batch, gas, self.confirmations, access_list).await
+            {
+                Ok(()) => self.complete(nonce)?,
+                Err(err) => {
+                    warn!("Outstanding batch at nonce {nonce} failed to resubmit ({err}); left persisted for retry");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Records a batch as in-flight and persists the pending set before it is
+    /// sent, so a crash between send and confirmation can be recovered.
+    pub fn record(&mut self, batch: PendingBatch) -> Result<()> {
+        // A batch of N numbers consumes N QRN nonces starting at batch.nonce.
+        let consumed = batch.data.len().max(1) as u64;
+        self.next_nonce = self.next_nonce.max(batch.nonce + consumed);
+        self.pending.insert(batch.nonce, batch);
+        self.persist()
+    }
+
+    /// Marks the batch at `nonce` complete and persists the shrunken set.
+    pub fn complete(&mut self, nonce: u64) -> Result<()> {
+        self.pending.remove(&nonce);
+        self.persist()
+    }
+
+    /// Confirmation depth required before a batch is considered complete.
+    pub fn confirmations(&self) -> u64 {
+        self.confirmations
+    }
+
+    /// Submits `batch`, waiting for the configured confirmation depth, and keeps
+    /// the persisted pending set in sync across the transaction's lifetime.
+    pub async fn submit<P, N>(
+        &mut self,
+        contract: &StorageNumber::StorageNumberInstance<P, N>,
+        wallet: Address,
+        batch: PendingBatch,
+        gas: &GasStrategy,
+        access_list: &AccessListMode,
+    ) -> Result<()>
+    where
+        P: Provider<N>,
+        N: Network,
+    {
+        let nonce = batch.nonce;
+        self.record(batch.clone())?;
+        submit_batch(contract, wallet, &batch, gas, self.confirmations, access_list).await?;
+        self.complete(nonce)
+    }
+
+    /// Atomically writes the pending set to disk.
+    fn persist(&self) -> Result<()> {
+        let state = PersistedState { pending: self.pending.values().cloned().collect() };
+        let serialized = serde_json::to_vec_pretty(&state)?;
+        write_atomic(&self.store_path, &serialized)
+    }
+}
+
+/// Writes `bytes` to `path` via a temporary file and a rename, so a crash never
+/// leaves a half-written pending set behind.
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
@@ -11,16 +11,27 @@ mod offline;
 
 use alloy::{
     eips::BlockNumberOrTag,
+    network::Network,
     primitives::{Address, U256},
     providers::{Provider, ProviderBuilder, WsConnect},
-    rpc::types::Filter,
+    rpc::types::{Filter, Log},
     signers::local::PrivateKeySigner,
 };
 use dotenv::dotenv;
 use foundry_contracts::storage_number::StorageNumber;
 use futures_util::stream::StreamExt;
-use log::{debug, info};
-use qrng_controller::send_random_number;
+use log::{debug, info, warn};
+use qrng_controller::{
+    access_list::AccessListMode,
+    build_batch,
+    gas::GasStrategy,
+    key_source::KeySource,
+    lifecycle::NonceManager,
+    rotation::{derive_rotation, rotate_onchain_key},
+    subscription::{backfill_logs, Backoff, BackfillCursor},
+    SignedGenerator,
+};
+use qrng_controller::verify::verify_signed_numbers;
 use std::{env, str::FromStr};
 
 #[tokio::main]
@@ -33,8 +44,11 @@ async fn main() -> color_eyre::Result<()> {
     let wallet_address: Address = Address::from_str(&env::var("WALLET")?)?;
     info!("Using wallet address: {wallet_address:?}");
 
-    let private_key = env::var("PRIVATE_KEY_WALLET")?;
-    let wallet: PrivateKeySigner = private_key.parse()?;
+    let key_source = KeySource::from_env()?;
+    let wallet: PrivateKeySigner = key_source.signer()?;
+
+    let gas_strategy = GasStrategy::from_env()?;
+    let access_list = AccessListMode::from_env()?;
 
     // WS and http provider
     let provider = ProviderBuilder::new()
@@ -55,7 +69,7 @@ async fn main() -> color_eyre::Result<()> {
     };
 
     #[cfg(feature = "testing")]
-    let mut interface = offline::OfflineGenerator::new();
+    let mut interface = offline::OfflineGenerator::from_key_source(&key_source)?;
 
     // Creating subscriber to generation event logs
     let filter = Filter::new()
@@ -66,42 +80,187 @@ async fn main() -> color_eyre::Result<()> {
             "AskElements(address,(uint256,(bytes32,uint256[2],uint256[2],uint256,uint256,address,uint256[2],uint256[2],uint256)))",
         ])
     ;
-    let sub = ws_provider.subscribe_logs(&filter).await?;
-    let mut stream = sub.into_stream();
 
-    info!("Asking generation left");
-    let _ = storage_contract.emitGenerationCapacity(wallet_address).send().await?;
-    let mut nonce = storage_contract.getNonce(wallet_address).call().await?;
+    // Durable transaction lifecycle: reload any persisted in-flight batches and
+    // reconcile them against chain state before subscribing to live events.
+    let store_path = env::var("PENDING_STORE_PATH").unwrap_or_else(|_| "pending.json".to_string());
+    let confirmations =
+        env::var("CONFIRMATIONS").ok().and_then(|value| value.parse().ok()).unwrap_or(3);
+    let mut tx_manager = NonceManager::load(store_path, confirmations)?;
+    tx_manager.reconcile(&storage_contract, wallet_address, &gas_strategy, &access_list).await?;
 
-    let mut generation_left = U256::ZERO;
-    while let Some(log) = stream.next().await {
-        debug!("Received event");
+    // Graceful key cutover: reconcile has resubmitted the persisted batches
+    // committed under the old key (a batch whose resubmission failed stays
+    // persisted), so if a rotation target is configured we register the new key,
+    // switch the generator, and prove a fresh proof verifies against it before
+    // resuming under the new identity.
+    if env::var("ROTATE_KEY").is_ok() {
+        let new_key_source = KeySource::from_env_prefixed("NEW_")?;
+        let rotation = derive_rotation(&new_key_source)?;
+        rotate_onchain_key(
+            &storage_contract,
+            wallet_address,
+            &rotation,
+            &gas_strategy,
+            confirmations,
+        )
+        .await?;
 
-        if let Ok(event) = log.log_decode::<StorageNumber::AskElements>() {
-            if event.inner.data.qrng == wallet_address {
-                generation_left += U256::from(1);
-            }
-        } else if let Ok(event) = log.log_decode::<StorageNumber::GenerationLeft>() {
-            if event.inner.data.qrng == wallet_address && event.inner.data.number != U256::ZERO {
-                generation_left = event.inner.data.number;
+        // Software-held keys switch in-process; hardware-held keys must be
+        // reconfigured on the device out of band before the controller restarts
+        // under the rotation target.
+        #[cfg(feature = "testing")]
+        interface.rotate_to(&new_key_source)?;
+
+        // Retire the old key only after a freshly generated proof verifies and is
+        // bound to the newly registered public key. This guards both paths: the
+        // mock generator that just switched in-process, and the hardware device
+        // that must already be serving the new key.
+        let probe = build_batch(&mut interface, U256::from(1), 0, wallet_address)?;
+        verify_signed_numbers(&probe.to_signed_numbers(), wallet_address)?;
+        if probe.proofs[0].pk != rotation.pk {
+            return Err(color_eyre::eyre::eyre!(
+                "freshly generated proof does not match the newly registered key"
+            ));
+        }
+        info!("Key rotation complete; now signing under {:?}", rotation.signer);
+    }
+
+    let mut generation_left;
+    let mut cursor = BackfillCursor::new(ws_provider.get_block_number().await?);
+    let mut backoff = Backoff::default();
+
+    // Supervised subscription: a dropped WebSocket no longer terminates the
+    // controller. On every (re)connect we reconcile nonce/pending state, re-read
+    // the authoritative generation-left, and backfill logs missed while
+    // disconnected before resuming the live stream.
+    loop {
+        let stream = match ws_provider.subscribe_logs(&filter).await {
+            Ok(sub) => sub.into_stream(),
+            Err(err) => {
+                let delay = backoff.next_delay();
+                warn!("Subscription failed ({err}); reconnecting in {delay:?}");
+                tokio::time::sleep(delay).await;
+                continue;
             }
-        } else {
-            debug!("{log:?}")
+        };
+        backoff.reset();
+        info!("Subscribed to generation events from block {}", cursor.from_block());
+
+        // A transient RPC failure here must not tear down the controller: log it
+        // and retry on the next reconnect rather than propagating out of `main`.
+        if let Err(err) = tx_manager
+            .reconcile(&storage_contract, wallet_address, &gas_strategy, &access_list)
+            .await
+        {
+            let delay = backoff.next_delay();
+            warn!("Reconcile on reconnect failed ({err}); retrying in {delay:?}");
+            tokio::time::sleep(delay).await;
+            continue;
         }
 
-        debug!("Gen left {generation_left}");
-        if generation_left >= U256::from(5) {
-            send_random_number(
+        // Rebuild the in-memory counter from the contract rather than guessing.
+        generation_left = U256::ZERO;
+        info!("Asking generation left");
+        let _ = storage_contract.emitGenerationCapacity(wallet_address).send().await?;
+
+        // Replay anything emitted while we were disconnected.
+        for log in backfill_logs(&ws_provider, &filter, cursor.from_block()).await? {
+            handle_log(
+                log,
+                wallet_address,
+                &mut generation_left,
+                &mut cursor,
+                &mut interface,
+                &mut tx_manager,
                 &storage_contract,
+                &gas_strategy,
+                &access_list,
+            )
+            .await?;
+        }
+
+        let mut stream = stream;
+        while let Some(log) = stream.next().await {
+            handle_log(
+                log,
                 wallet_address,
-                generation_left,
+                &mut generation_left,
+                &mut cursor,
                 &mut interface,
-                nonce,
+                &mut tx_manager,
+                &storage_contract,
+                &gas_strategy,
+                &access_list,
             )
             .await?;
-            generation_left = U256::ZERO;
-            nonce = storage_contract.getNonce(wallet_address).call().await?;
         }
+
+        let delay = backoff.next_delay();
+        warn!("Event stream closed; reconnecting in {delay:?}");
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Processes a single generation event, updating the in-memory counter and
+/// submitting a batch once enough requests have accumulated.
+///
+/// Shared by the backfill replay and the live stream so both paths stay in sync.
+#[allow(clippy::too_many_arguments)]
+async fn handle_log<P, N>(
+    log: Log,
+    wallet_address: Address,
+    generation_left: &mut U256,
+    cursor: &mut BackfillCursor,
+    interface: &mut impl SignedGenerator,
+    tx_manager: &mut NonceManager,
+    storage_contract: &StorageNumber::StorageNumberInstance<P, N>,
+    gas_strategy: &GasStrategy,
+    access_list: &AccessListMode,
+) -> color_eyre::Result<()>
+where
+    P: Provider<N>,
+    N: Network,
+{
+    debug!("Received event");
+
+    // Deduplicate against the backfill overlap: skip events already processed at
+    // this (block, log_index). Events without positions (pending) are processed.
+    if let (Some(block_number), Some(log_index)) = (log.block_number, log.log_index) {
+        if !cursor.observe(block_number, log_index) {
+            debug!("Skipping already-processed event at {block_number}/{log_index}");
+            return Ok(());
+        }
+    }
+
+    if let Ok(event) = log.log_decode::<StorageNumber::AskElements>() {
+        if event.inner.data.qrng == wallet_address {
+            *generation_left += U256::from(1);
+        }
+    } else if let Ok(event) = log.log_decode::<StorageNumber::GenerationLeft>() {
+        if event.inner.data.qrng == wallet_address && event.inner.data.number != U256::ZERO {
+            *generation_left = event.inner.data.number;
+        }
+    } else {
+        debug!("{log:?}")
+    }
+
+    debug!("Gen left {generation_left}");
+    if *generation_left >= U256::from(5) {
+        let count = generation_left.into_limbs()[0];
+        let nonce = tx_manager.allocate_nonce(count);
+        let batch = build_batch(interface, *generation_left, nonce, wallet_address)?;
+
+        // A revert or exhausted resubmission budget stays local to this batch:
+        // it remains persisted and is retried on the next reconnect, rather than
+        // tearing down the supervised stream loop.
+        if let Err(err) = tx_manager
+            .submit(storage_contract, wallet_address, batch, gas_strategy, access_list)
+            .await
+        {
+            warn!("Batch at QRN nonce {nonce} failed to submit ({err}); left persisted for retry");
+        }
+        *generation_left = U256::ZERO;
     }
     Ok(())
 }
@@ -18,31 +18,39 @@ use libecvrf::{
     secp256k1::SecretKey as ProverSecretKey,
     ECVRF,
 };
-use qrng_controller::{SignedGenerator, SignedNumber};
+use qrng_controller::{key_source::KeySource, SignedGenerator, SignedNumber};
 
 use rand::{self, Rng};
 
-// FOR OFFLINE TEST ONLY!
-// Need to adapt the public key in deployment script of the solidity contract
-const PRIVATE_KEY: &[u8; 32] = &[
-    0xa1, 0x2b, 0x45, 0xc8, 0x9d, 0x3e, 0x47, 0xa4, 0x56, 0xf8, 0xf8, 0x9b, 0xa6, 0x7c, 0x85, 0xc4,
-    0xd2, 0xc6, 0x72, 0x01, 0x91, 0xb4, 0x8f, 0x79, 0xd4, 0xe5, 0x68, 0xf1, 0xa6, 0x47, 0xc3, 0xf1,
-];
-
 pub struct OfflineGenerator {
     private_key_sign: SignatureSecretKey,
     private_key_prover: ProverSecretKey,
 }
 
 impl OfflineGenerator {
-    /// Creates a new `OfflineGenerator` using a static private key.
+    /// Creates a new `OfflineGenerator` from a [`KeySource`].
+    ///
+    /// The signing and prover keys are derived from the same secret, so the VRF
+    /// public key stays consistent with the signer address.
+    ///
+    /// # Errors
+    /// Returns an error if the key source cannot yield a valid signing or prover key.
+    pub fn from_key_source(key_source: &KeySource) -> color_eyre::Result<Self> {
+        let private_key_sign = key_source.signing_key()?;
+        let private_key_prover = key_source.prover_key()?;
+        Ok(Self { private_key_sign, private_key_prover })
+    }
+
+    /// Switches the generator to a new [`KeySource`] after an on-chain rotation
+    /// has been confirmed. Subsequent QRNs are signed and proven under the new
+    /// key.
     ///
-    /// # Panics
-    /// Panics if the private key cannot be parsed into either the signature or prover key format.
-    pub fn new() -> Self {
-        let private_key_sign = SignatureSecretKey::from_raw(PRIVATE_KEY).unwrap();
-        let private_key_prover = ProverSecretKey::parse_slice(PRIVATE_KEY).unwrap();
-        Self { private_key_sign, private_key_prover }
+    /// # Errors
+    /// Returns an error if the new key source cannot yield a valid signing or prover key.
+    pub fn rotate_to(&mut self, key_source: &KeySource) -> color_eyre::Result<()> {
+        self.private_key_sign = key_source.signing_key()?;
+        self.private_key_prover = key_source.prover_key()?;
+        Ok(())
     }
 }
 
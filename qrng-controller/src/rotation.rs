@@ -0,0 +1,95 @@
+//! On-chain rotation of the VRF/signing key with graceful cutover.
+//!
+//! The prover/signer public key is fixed at contract deployment, so retiring or
+//! replacing a key would otherwise mean redeploying. [`rotate_onchain_key`]
+//! submits a freshly derived VRF public key and signer address to the storage
+//! contract and confirms the rotation; the controller binary then checks that a
+//! freshly generated proof verifies against the newly registered key before the
+//! old key is retired — adapting the `updateSeraiKey` rotation mechanism to this
+//! crate's signer/prover pair.
+//!
+//! Graceful cutover is the caller's responsibility: rotate only once all
+//! `generation_left` committed under the old key has been served. The controller
+//! binary reconciles before rotating, which resubmits the persisted outstanding
+//! batches; a batch whose resubmission fails stays persisted and the rotation
+//! still proceeds.
+
+use alloy::{
+    network::{Network, ReceiptResponse},
+    primitives::{Address, U256},
+    providers::Provider,
+};
+use color_eyre::{eyre::eyre, Result};
+use foundry_contracts::storage_number::StorageNumber;
+use libecvrf::secp256k1::{curve::Affine, PublicKey};
+use log::info;
+
+use crate::{gas::GasStrategy, key_source::KeySource};
+
+/// The registered identity derived from a [`KeySource`]: the VRF public key
+/// coordinates and the matching signer address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyRotation {
+    /// VRF public key `[x, y]`, as registered on-chain.
+    pub pk: [U256; 2],
+    /// Signer address recovered from the Ethereum signing key.
+    pub signer: Address,
+}
+
+/// Derives the on-chain identity (`pk`, `signer`) for the key behind
+/// `key_source`.
+///
+/// Both are derived from the same secret, so the registered VRF public key and
+/// the signer address stay consistent.
+pub fn derive_rotation(key_source: &KeySource) -> Result<KeyRotation> {
+    let prover = key_source.prover_key()?;
+    let mut public: Affine = PublicKey::from_secret_key(&prover).into();
+    public.x.normalize();
+    public.y.normalize();
+    let pk = [U256::from_be_bytes(public.x.b32()), U256::from_be_bytes(public.y.b32())];
+
+    let signing = key_source.signing_key()?;
+    let signer = Address::from_slice(signing.public().address());
+
+    Ok(KeyRotation { pk, signer })
+}
+
+/// Submits `rotation` to the storage contract and confirms it took effect.
+///
+/// Waits `confirmations` blocks, checks the receipt status, and returns `Ok(())`
+/// once the rotation is confirmed. It does not check any proof against the new
+/// `pk`; the caller switches its generator to the new key and verifies a freshly
+/// generated proof before the old key is retired.
+///
+/// # Errors
+///
+/// Returns an error if the rotation transaction reverts or does not confirm.
+pub async fn rotate_onchain_key<P, N>(
+    contract: &StorageNumber::StorageNumberInstance<P, N>,
+    wallet: Address,
+    rotation: &KeyRotation,
+    gas: &GasStrategy,
+    confirmations: u64,
+) -> Result<()>
+where
+    P: Provider<N>,
+    N: Network,
+{
+    info!("Submitting key rotation: pk={:?} signer={:?}", rotation.pk, rotation.signer);
+
+    let fees = gas.resolve(contract.provider()).await?;
+    let mut call = contract.updatePublicKey(rotation.pk, rotation.signer).from(wallet);
+    call = match fees {
+        crate::gas::ResolvedFees::Legacy { gas_price } => call.gas_price(gas_price),
+        crate::gas::ResolvedFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => {
+            call.max_fee_per_gas(max_fee_per_gas).max_priority_fee_per_gas(max_priority_fee_per_gas)
+        }
+    };
+
+    let receipt =
+        call.send().await?.with_required_confirmations(confirmations).get_receipt().await?;
+    if !receipt.status() {
+        return Err(eyre!("key rotation transaction reverted"));
+    }
+    Ok(())
+}
@@ -0,0 +1,143 @@
+//! Resilient event subscription helpers.
+//!
+//! A bare `subscribe_logs` stream terminates permanently when the WebSocket
+//! connection drops, and any events emitted while disconnected are lost. The
+//! controller wraps its subscription in a supervised loop that reconnects with
+//! [`Backoff`] and, on each reconnect, backfills the logs emitted while
+//! disconnected via [`backfill_logs`] before resuming the live stream.
+
+use std::{collections::HashSet, time::Duration};
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    network::Network,
+    providers::Provider,
+    rpc::types::{Filter, Log},
+};
+use color_eyre::Result;
+
+/// Initial reconnect delay.
+const BASE_DELAY: Duration = Duration::from_secs(1);
+/// Ceiling on the reconnect delay.
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Exponential backoff for reconnect attempts, capped at [`MAX_DELAY`].
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    current: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self { current: BASE_DELAY }
+    }
+}
+
+impl Backoff {
+    /// Returns the next delay and doubles the internal counter, saturating at
+    /// [`MAX_DELAY`].
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(MAX_DELAY);
+        delay
+    }
+
+    /// Resets the delay after a successful (re)connection.
+    pub fn reset(&mut self) {
+        self.current = BASE_DELAY;
+    }
+}
+
+/// Fetches the logs matching `filter` emitted between `from_block` and the
+/// latest block, so events missed while disconnected can be replayed.
+pub async fn backfill_logs<P, N>(provider: &P, filter: &Filter, from_block: u64) -> Result<Vec<Log>>
+where
+    P: Provider<N>,
+    N: Network,
+{
+    let filter = filter
+        .clone()
+        .from_block(BlockNumberOrTag::Number(from_block))
+        .to_block(BlockNumberOrTag::Latest);
+    Ok(provider.get_logs(&filter).await?)
+}
+
+/// Tracks which events have already been processed, at `(block, log_index)`
+/// granularity, so backfill after a reconnect neither re-processes nor skips
+/// events — including multiple matching events sharing a single block.
+///
+/// The cursor re-queries the last touched block inclusively (see
+/// [`from_block`](Self::from_block)); [`observe`](Self::observe) deduplicates the
+/// overlap.
+#[derive(Debug, Clone)]
+pub struct BackfillCursor {
+    last_block: u64,
+    seen: HashSet<u64>,
+}
+
+impl BackfillCursor {
+    /// Starts tracking from `start_block` with nothing yet processed.
+    pub fn new(start_block: u64) -> Self {
+        Self { last_block: start_block, seen: HashSet::new() }
+    }
+
+    /// The block to backfill from, inclusive of the last touched block so a
+    /// second event in that block is not missed.
+    pub fn from_block(&self) -> u64 {
+        self.last_block
+    }
+
+    /// Records an event at `(block_number, log_index)` and reports whether it is
+    /// new and should be processed.
+    ///
+    /// Advancing to a higher block clears the per-block dedupe set; events below
+    /// the current block, or already seen within it, return `false`.
+    pub fn observe(&mut self, block_number: u64, log_index: u64) -> bool {
+        if block_number < self.last_block {
+            return false;
+        }
+        if block_number > self.last_block {
+            self.last_block = block_number;
+            self.seen.clear();
+        }
+        self.seen.insert(log_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observe_keeps_second_event_in_same_block() {
+        let mut cursor = BackfillCursor::new(100);
+        assert!(cursor.observe(100, 0));
+        // A second event sharing the block must still be processed once.
+        assert!(cursor.observe(100, 1));
+        // Re-seeing either on a backfill overlap is skipped.
+        assert!(!cursor.observe(100, 0));
+        assert!(!cursor.observe(100, 1));
+        // The block is re-queried inclusively so the overlap can be deduped.
+        assert_eq!(cursor.from_block(), 100);
+    }
+
+    #[test]
+    fn observe_advances_and_clears_on_new_block() {
+        let mut cursor = BackfillCursor::new(100);
+        assert!(cursor.observe(100, 5));
+        assert!(cursor.observe(101, 5));
+        assert_eq!(cursor.from_block(), 101);
+        // Stale lower blocks are ignored.
+        assert!(!cursor.observe(100, 9));
+    }
+
+    #[test]
+    fn backoff_doubles_and_resets() {
+        let mut backoff = Backoff::default();
+        let first = backoff.next_delay();
+        let second = backoff.next_delay();
+        assert_eq!(second, first * 2);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), first);
+    }
+}
@@ -0,0 +1,407 @@
+//! Pre-submission verification of signed QRNs.
+//!
+//! Before a batch is handed to `addRandomNumbers`/`addRandomNumber`, every
+//! [`SignedNumber`](crate::SignedNumber) is replayed locally against the same
+//! checks the storage contract performs on-chain. A number that would revert is
+//! rejected here, so the controller can skip or regenerate it instead of burning
+//! gas on a guaranteed revert.
+//!
+//! The ECDSA path mirrors the `secp256k1_ecdsa_recover` + `keccak_256`
+//! address-recovery used to validate Ethereum-signed claims, applied defensively
+//! on the producer side; the VRF path replays the proof witnesses through the
+//! curve arithmetic the ECVRF verifier contract relies on.
+
+use alloy::primitives::{keccak256, Address, U256};
+use foundry_contracts::storage_number::StorageNumber::{
+    EcvrfContractProofSolidity, SignatureSolidity,
+};
+use libecvrf::{
+    helper,
+    secp256k1::{
+        curve::{Affine, ECMultContext, Field, Jacobian, Scalar},
+        recover, Message, RecoveryId, Signature,
+    },
+};
+
+use crate::SignedNumber;
+
+/// A single check that a [`SignedNumber`] failed during local verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationFailure {
+    /// The `(r, s, v)` triple could not be parsed or ECDSA recovery produced no
+    /// public key.
+    SignatureUnrecoverable,
+    /// Recovery succeeded but the derived address does not match the signer
+    /// address embedded in `SignatureSolidity.wallet`.
+    SignerMismatch { recovered: Address, expected: Address },
+    /// A curve point carried by the proof (`pk`, `gamma`, `cGammaWitness` or
+    /// `sHashWitness`) is not a valid, non-infinity point on secp256k1.
+    VrfPointOffCurve,
+    /// `address(s·G − c·pk)` does not equal `uWitness`, so the response `s` is
+    /// inconsistent with the challenge `c`, the public key and the nonce witness.
+    VrfWitnessMismatch,
+    /// `c · gamma` does not reproduce `cGammaWitness`.
+    VrfGammaWitnessMismatch,
+    /// `s · H` does not reproduce `sHashWitness`, where `H = hashToCurve(pk, alpha)`.
+    VrfHashWitnessMismatch,
+    /// The challenge recomputed from the witnesses does not equal the `c` the
+    /// proof carries, so `c` was not honestly derived by Fiat–Shamir.
+    VrfChallengeMismatch,
+}
+
+/// The set of verification failures found in a batch, keyed by the index of the
+/// offending `SignedNumber`.
+///
+/// Returned by [`verify_signed_numbers`] so the controller can decide which
+/// elements to skip or regenerate rather than submitting a batch that is certain
+/// to revert.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerificationError {
+    pub failures: Vec<(usize, VerificationFailure)>,
+}
+
+impl std::fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} signed number(s) failed local verification:", self.failures.len())?;
+        for (index, failure) in &self.failures {
+            write!(f, " [#{index}: {failure:?}]")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+/// Verifies every `SignedNumber` in `numbers` against the signer `expected_signer`.
+///
+/// Each element is checked for both a recoverable signature matching
+/// `expected_signer` and an internally consistent VRF proof. All failures are
+/// collected so the caller sees the full picture in one pass.
+///
+/// # Errors
+///
+/// Returns a [`VerificationError`] listing every `(index, failure)` pair when at
+/// least one element fails; `Ok(())` when the whole batch would be accepted
+/// on-chain.
+pub fn verify_signed_numbers(
+    numbers: &[SignedNumber],
+    expected_signer: Address,
+) -> Result<(), VerificationError> {
+    let mut failures = vec![];
+    for (index, (_number, hash, signature, proof)) in numbers.iter().enumerate() {
+        if let Err(failure) = verify_signature(hash.as_slice(), signature, expected_signer) {
+            failures.push((index, failure));
+        }
+        if let Err(failure) = verify_vrf_proof(proof) {
+            failures.push((index, failure));
+        }
+    }
+    if failures.is_empty() { Ok(()) } else { Err(VerificationError { failures }) }
+}
+
+/// Recovers the signer of `hash` from `signature` and compares it to `expected`.
+///
+/// `hash` is the stored digest `keccak256(number‖nonce)`; the recovery id is
+/// `v - 27` (undoing the Ethereum `+27` convention). The recovered 64-byte
+/// uncompressed public key is hashed with keccak256 and its low 20 bytes form the
+/// address that must equal `expected`.
+fn verify_signature(
+    hash: &[u8],
+    signature: &SignatureSolidity,
+    expected: Address,
+) -> Result<(), VerificationFailure> {
+    let message =
+        Message::parse_slice(hash).map_err(|_| VerificationFailure::SignatureUnrecoverable)?;
+
+    let mut rs = [0u8; 64];
+    rs[..32].copy_from_slice(signature.r.as_slice());
+    rs[32..].copy_from_slice(signature.s.as_slice());
+    let parsed = Signature::parse_standard_slice(&rs)
+        .map_err(|_| VerificationFailure::SignatureUnrecoverable)?;
+
+    let recovery_id = RecoveryId::parse(signature.v.wrapping_sub(27))
+        .map_err(|_| VerificationFailure::SignatureUnrecoverable)?;
+
+    let public_key = recover(&message, &parsed, &recovery_id)
+        .map_err(|_| VerificationFailure::SignatureUnrecoverable)?;
+
+    // serialize() yields the 65-byte uncompressed form (0x04 ‖ X ‖ Y); the
+    // address is keccak256 over the 64-byte X‖Y body.
+    let uncompressed = public_key.serialize();
+    let recovered = Address::from_slice(&keccak256(&uncompressed[1..])[12..]);
+
+    if recovered == expected {
+        Ok(())
+    } else {
+        Err(VerificationFailure::SignerMismatch { recovered, expected })
+    }
+}
+
+/// Replays the ECVRF proof through the same arithmetic the on-chain verifier
+/// contract performs, rejecting anything that would revert there.
+///
+/// `pk`, `gamma` and both witness points must be valid, non-infinity curve
+/// points. The hash point `H = hashToCurve(pk, alpha)` is reconstructed from the
+/// proof's own `alpha`, then the proof is checked against the four relations the
+/// verifier relies on:
+///
+/// * `s · H == sHashWitness`,
+/// * `c · gamma == cGammaWitness`,
+/// * `address(s·G − c·pk) == uWitness` (the nonce commitment the challenge binds),
+/// * `c == scalarFromCurvePoints(H, pk, gamma, uWitness, cGammaWitness + sHashWitness)`.
+///
+/// The final check recomputes the Fiat–Shamir challenge from the witnesses, so a
+/// proof carrying a forged `c`, `s`, `uWitness` or `sHashWitness` is rejected
+/// rather than submitted and reverted.
+fn verify_vrf_proof(proof: &EcvrfContractProofSolidity) -> Result<(), VerificationFailure> {
+    let pk = affine_from_coords(&proof.pk).ok_or(VerificationFailure::VrfPointOffCurve)?;
+    let gamma = affine_from_coords(&proof.gamma).ok_or(VerificationFailure::VrfPointOffCurve)?;
+    let c_gamma =
+        affine_from_coords(&proof.cGammaWitness).ok_or(VerificationFailure::VrfPointOffCurve)?;
+    let s_hash =
+        affine_from_coords(&proof.sHashWitness).ok_or(VerificationFailure::VrfPointOffCurve)?;
+
+    let c = scalar_from_u256(proof.c);
+    let s = scalar_from_u256(proof.s);
+
+    // H = hashToCurve(pk, alpha); derived the same way the prover did, so the
+    // witness products below are checked against the honest hash point.
+    let mut alpha = Scalar::default();
+    let _ = alpha.set_b32(&proof.alpha.0);
+    let mut hash_point = helper::hash_to_curve_prefix(&alpha, &pk);
+    hash_point.x.normalize();
+    hash_point.y.normalize();
+
+    // s · H must reproduce sHashWitness.
+    if !affine_eq(&scalar_mul(&hash_point, &s), &s_hash) {
+        return Err(VerificationFailure::VrfHashWitnessMismatch);
+    }
+
+    // c · gamma must reproduce cGammaWitness.
+    if !affine_eq(&scalar_mul(&gamma, &c), &c_gamma) {
+        return Err(VerificationFailure::VrfGammaWitnessMismatch);
+    }
+
+    // u = s·G − c·pk; the verifier recovers this commitment and checks its
+    // address against uWitness, so a wrong s, c, pk or uWitness fails here.
+    let mut u = Jacobian::default();
+    let mut pk_jac = Jacobian::default();
+    pk_jac.set_ge(&pk);
+    ecmult_context().ecmult(&mut u, &pk_jac, &-scalar_from_u256(proof.c), &s);
+    let mut u_affine = Affine::from_gej(&u);
+    u_affine.x.normalize();
+    u_affine.y.normalize();
+    if point_address(&u_affine) != proof.uWitness {
+        return Err(VerificationFailure::VrfWitnessMismatch);
+    }
+
+    // Recompute the challenge from (H, pk, gamma, uWitness, c·gamma + s·H) and
+    // require it to match the c the proof carries.
+    let v = point_add(&c_gamma, &s_hash);
+    let recomputed = scalar_from_curve_points(&hash_point, &proof.pk, &proof.gamma, proof.uWitness, &v);
+    if recomputed != proof.c {
+        return Err(VerificationFailure::VrfChallengeMismatch);
+    }
+
+    Ok(())
+}
+
+/// Ethereum address of a curve point: the low 20 bytes of
+/// `keccak256(X ‖ Y)` over the 64-byte affine encoding.
+fn point_address(point: &Affine) -> Address {
+    let mut encoded = [0u8; 64];
+    encoded[..32].copy_from_slice(&point.x.b32());
+    encoded[32..].copy_from_slice(&point.y.b32());
+    Address::from_slice(&keccak256(encoded)[12..])
+}
+
+/// Computes `scalar · point` and returns the normalized affine result.
+fn scalar_mul(point: &Affine, scalar: &Scalar) -> Affine {
+    let mut jac = Jacobian::default();
+    jac.set_ge(point);
+    let mut product = Jacobian::default();
+    ecmult_context().ecmult(&mut product, &jac, scalar, &Scalar::from_int(0));
+    let mut affine = Affine::from_gej(&product);
+    affine.x.normalize();
+    affine.y.normalize();
+    affine
+}
+
+/// Adds two affine points and returns the normalized affine sum.
+fn point_add(a: &Affine, b: &Affine) -> Affine {
+    let mut jac = Jacobian::default();
+    jac.set_ge(a);
+    let sum = jac.add_ge(b);
+    let mut affine = Affine::from_gej(&sum);
+    affine.x.normalize();
+    affine.y.normalize();
+    affine
+}
+
+/// Recomputes the VRF challenge scalar the way the verifier contract's
+/// `scalarFromCurvePoints` does: `keccak256(prefix ‖ H ‖ pk ‖ gamma ‖ v ‖
+/// uWitness)`, packed as the contract encodes it (32-byte coordinates, the
+/// 20-byte `uWitness` address, and a leading `0x02` domain-separation prefix).
+fn scalar_from_curve_points(
+    hash_point: &Affine,
+    pk: &[U256; 2],
+    gamma: &[U256; 2],
+    u_witness: Address,
+    v: &Affine,
+) -> U256 {
+    let mut buf = Vec::with_capacity(32 * 9 + 20);
+    buf.extend_from_slice(&U256::from(2).to_be_bytes::<32>());
+    buf.extend_from_slice(&hash_point.x.b32());
+    buf.extend_from_slice(&hash_point.y.b32());
+    buf.extend_from_slice(&pk[0].to_be_bytes::<32>());
+    buf.extend_from_slice(&pk[1].to_be_bytes::<32>());
+    buf.extend_from_slice(&gamma[0].to_be_bytes::<32>());
+    buf.extend_from_slice(&gamma[1].to_be_bytes::<32>());
+    buf.extend_from_slice(&v.x.b32());
+    buf.extend_from_slice(&v.y.b32());
+    buf.extend_from_slice(u_witness.as_slice());
+    U256::from_be_bytes(keccak256(buf).0)
+}
+
+/// Builds an on-curve [`Affine`] from a `[x, y]` coordinate pair, returning
+/// `None` when the point is the identity or does not satisfy the curve equation.
+fn affine_from_coords(coords: &[U256; 2]) -> Option<Affine> {
+    let mut x = Field::default();
+    let mut y = Field::default();
+    if !x.set_b32(&coords[0].to_be_bytes()) || !y.set_b32(&coords[1].to_be_bytes()) {
+        return None;
+    }
+    let mut point = Affine::default();
+    point.set_xy(&x, &y);
+    if point.is_infinity() || !point.is_valid_var() {
+        return None;
+    }
+    Some(point)
+}
+
+fn scalar_from_u256(value: U256) -> Scalar {
+    let mut scalar = Scalar::default();
+    let _ = scalar.set_b32(&value.to_be_bytes());
+    scalar
+}
+
+fn affine_eq(a: &Affine, b: &Affine) -> bool {
+    a.x.eq_var(&b.x) && a.y.eq_var(&b.y)
+}
+
+/// Returns a process-wide multiplication context, built lazily on first use.
+fn ecmult_context() -> &'static ECMultContext {
+    use std::sync::OnceLock;
+    static CONTEXT: OnceLock<Box<ECMultContext>> = OnceLock::new();
+    CONTEXT.get_or_init(ECMultContext::new_boxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::FixedBytes;
+    use libecvrf::{
+        extends::ScalarExtend,
+        secp256k1::{sign, PublicKey, SecretKey},
+        ECVRF,
+    };
+
+    /// A fixed, non-trivial secret key used to produce a deterministic signature.
+    const SECRET: [u8; 32] = [
+        0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+        0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+        0x10, 0x20,
+    ];
+
+    /// Signs `hash` with [`SECRET`] and returns the matching signer address and a
+    /// ready-to-verify `SignatureSolidity`.
+    fn sign_fixture(hash: &[u8; 32]) -> (Address, SignatureSolidity) {
+        let secret = SecretKey::parse(&SECRET).unwrap();
+        let public = PublicKey::from_secret_key(&secret);
+        let address = Address::from_slice(&keccak256(&public.serialize()[1..])[12..]);
+
+        let message = Message::parse(hash);
+        let (signature, recovery_id) = sign(&message, &secret);
+        let serialized = signature.serialize();
+        let sol = SignatureSolidity {
+            r: FixedBytes::from_slice(&serialized[..32]),
+            s: FixedBytes::from_slice(&serialized[32..]),
+            v: recovery_id.serialize() + 27,
+            wallet: address,
+        };
+        (address, sol)
+    }
+
+    #[test]
+    fn verify_signature_accepts_matching_signer() {
+        let hash = [0x42u8; 32];
+        let (address, signature) = sign_fixture(&hash);
+        assert_eq!(verify_signature(&hash, &signature, address), Ok(()));
+    }
+
+    #[test]
+    fn verify_signature_rejects_wrong_signer() {
+        let hash = [0x42u8; 32];
+        let (_address, signature) = sign_fixture(&hash);
+        let wrong = Address::repeat_byte(0xab);
+        assert!(matches!(
+            verify_signature(&hash, &signature, wrong),
+            Err(VerificationFailure::SignerMismatch { .. })
+        ));
+    }
+
+    /// Produces a genuine contract proof over `alpha` under [`SECRET`], packed
+    /// exactly as the controller submits it (mirrors `offline::create_vrf_proof`).
+    fn prove_fixture(alpha: &[u8; 32]) -> EcvrfContractProofSolidity {
+        let secret = SecretKey::parse(&SECRET).unwrap();
+        let ecvrf = ECVRF::new(secret);
+        let proof = ecvrf.prove_contract(&Scalar::from_bytes(alpha));
+
+        let mut pk: Affine = proof.pk.into();
+        pk.x.normalize();
+        pk.y.normalize();
+        EcvrfContractProofSolidity {
+            alpha: FixedBytes::<{ U256::BYTES }>::from_slice(&proof.alpha.b32()),
+            pk: [U256::from_be_bytes(pk.x.b32()), U256::from_be_bytes(pk.y.b32())],
+            gamma: [
+                U256::from_be_bytes(proof.gamma.x.b32()),
+                U256::from_be_bytes(proof.gamma.y.b32()),
+            ],
+            c: U256::from_be_bytes(proof.c.b32()),
+            s: U256::from_be_bytes(proof.s.b32()),
+            uWitness: Address::from_slice(&proof.witness_address.b32()[0..20]),
+            cGammaWitness: [
+                U256::from_be_bytes(proof.witness_gamma.x.b32()),
+                U256::from_be_bytes(proof.witness_gamma.y.b32()),
+            ],
+            sHashWitness: [
+                U256::from_be_bytes(proof.witness_hash.x.b32()),
+                U256::from_be_bytes(proof.witness_hash.y.b32()),
+            ],
+            zInv: U256::from_be_bytes(proof.inverse_z.b32()),
+        }
+    }
+
+    #[test]
+    fn verify_vrf_proof_accepts_honest_proof() {
+        let proof = prove_fixture(&[0x07u8; 32]);
+        assert_eq!(verify_vrf_proof(&proof), Ok(()));
+    }
+
+    #[test]
+    fn verify_vrf_proof_rejects_tampered_challenge() {
+        let mut proof = prove_fixture(&[0x07u8; 32]);
+        proof.c ^= U256::from(1);
+        // A flipped challenge breaks the c·gamma witness, the u-witness relation
+        // and the recomputed challenge; whichever fires first rejects the proof.
+        assert!(verify_vrf_proof(&proof).is_err());
+    }
+
+    #[test]
+    fn verify_vrf_proof_rejects_tampered_hash_witness() {
+        let mut proof = prove_fixture(&[0x07u8; 32]);
+        let other = prove_fixture(&[0x08u8; 32]);
+        proof.sHashWitness = other.sHashWitness;
+        assert_eq!(verify_vrf_proof(&proof), Err(VerificationFailure::VrfHashWitnessMismatch));
+    }
+}